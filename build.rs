@@ -0,0 +1,49 @@
+#[macro_use]
+extern crate clap;
+
+use clap::Shell;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Reuses the exact same `App` definition that powers argument parsing at runtime, so the
+// generated man page and completions can never drift from the options `ethaddrgen` actually
+// accepts.
+include!("src/cli.rs");
+
+const BIN_NAME: &str = "ethaddrgen";
+
+fn main() {
+    let out_dir = match env::var_os("OUT_DIR") {
+        Some(out_dir) => out_dir,
+        None => return,
+    };
+
+    let mut app = build_cli();
+
+    for &shell in &[Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+        app.gen_completions(BIN_NAME, shell, &out_dir);
+    }
+
+    let mut help = Vec::new();
+    app.write_long_help(&mut help)
+        .expect("Could not render the help text for the man page.");
+    let help = String::from_utf8(help).expect("Help text was not valid UTF-8.");
+
+    let man_page = format!(".TH {name} 1\n\
+.SH NAME\n\
+{name} \\- {about}\n\
+.SH SYNOPSIS\n\
+.B {name}\n\
+[OPTIONS] [PATTERN]...\n\
+.SH DESCRIPTION\n\
+.nf\n\
+{help}\n\
+.fi\n",
+                            name = BIN_NAME,
+                            about = env!("CARGO_PKG_DESCRIPTION"),
+                            help = help);
+
+    fs::write(Path::new(&out_dir).join(format!("{}.1", BIN_NAME)), man_page)
+        .expect("Could not write the generated man page.");
+}
@@ -0,0 +1,84 @@
+use clap::{App, Arg};
+
+/// Builds the command line interface definition. Kept separate from `main()` so that
+/// `build.rs` can reuse this exact `App` to generate the man page and shell completions at
+/// compile time, keeping the option definitions as the single source of truth.
+pub fn build_cli() -> App<'static, 'static> {
+    app_from_crate!()
+        .arg(Arg::with_name("regexp")
+             .long("regexp")
+             .short("e")
+             .help("Use regex pattern matching")
+             .long_help("By default, an address is accepted when the beginning matches one of the
+strings provided as the patterns. This flag changes the functionality from
+plain string matching to regex pattern matching.")
+             .conflicts_with("fuzzy"))
+        .arg(Arg::with_name("fuzzy")
+             .long("fuzzy")
+             .short("f")
+             .help("Use fuzzy subsequence pattern matching")
+             .long_help("By default, an address is accepted when the beginning matches one of the
+strings provided as the patterns. This flag changes the functionality to fuzzy
+matching: an address is accepted when the pattern's characters appear
+somewhere in the address in the same order, not necessarily at the beginning
+or next to each other. Each match gets a score that is higher when the
+pattern's characters line up more tightly; the score is printed with the
+result, and `--top` ranks addresses by it. Without `--top`, the first
+matching address is output whatever its score.")
+             .conflicts_with("regexp"))
+        .arg(Arg::with_name("quiet")
+             .long("quiet")
+             .short("q")
+             .help("Output only the results")
+             .long_help("Output only the resulting address and private key separated by a space.
+With `--fuzzy`, the match score follows as a third space-separated field."))
+        .arg(Arg::with_name("color")
+             .long("color")
+             .short("c")
+             .help("Changes the color formatting strategy")
+             .long_help("Changes the color formatting strategy in the following way:
+    always      -- Try very hard to emit colors. This includes
+                   emitting ANSI colors on Windows if the console
+                   API is unavailable.
+    always_ansi -- like always, except it never tries to use
+                   anything other than emitting ANSI color codes.
+    auto        -- Try to use colors, but don't force the issue.
+                   If the console isn't available on Windows, or
+                   if TERM=dumb, for example, then don't use colors.
+    never       -- Never emit colors.\n")
+             .takes_value(true)
+             .possible_values(&["always", "always_ansi", "auto", "never"])
+             .default_value("auto"))
+        .arg(Arg::with_name("stream")
+             .long("stream")
+             .short("s")
+             .help("Keep outputting results")
+             .long_help("Instead of outputting a single result, keep outputting until terminated."))
+        .arg(Arg::with_name("top")
+             .long("top")
+             .takes_value(true)
+             .value_name("N")
+             .validator(|value| match value.parse::<usize>() {
+                 Ok(0) => Err("The number of top results to keep must be at least 1".to_string()),
+                 Ok(_) => Ok(()),
+                 Err(error) => Err(error.to_string()),
+             })
+             .help("Collect the N best fuzzy matches instead of a single exact one")
+             .long_help("Instead of stopping at the first match, keep a running shortlist of the
+N best-scoring addresses seen so far and print an update whenever a newly
+generated address beats the worst entry still on the shortlist. Requires
+`--fuzzy` and `--stream`, since it only makes sense when matches are scored
+and the generator is left running indefinitely.")
+             .requires_all(&["fuzzy", "stream"]))
+        .arg(Arg::with_name("PATTERN")
+             .help("The pattern to match the address against")
+             .long_help("The pattern to match the address against.
+If no patterns are provided, they are read from the stdin (standard input),
+where each pattern is on a separate line.
+Addresses are outputted if the beginning matches one of these patterns.
+If the `--regexp` flag is used, the addresses are matched against these
+patterns as regex patterns, which replaces the basic string comparison.
+If the `--fuzzy` flag is used instead, a pattern matches as soon as its
+characters appear anywhere in the address in the same order.")
+             .multiple(true))
+}
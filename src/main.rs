@@ -10,24 +10,33 @@ extern crate tiny_keccak;
 extern crate num_cpus;
 extern crate termcolor;
 
-use clap::{Arg, ArgMatches};
+use clap::ArgMatches;
 use rand::OsRng;
 use regex::{Regex, RegexBuilder};
 use secp256k1::Secp256k1;
 use std::io::BufRead;
 use std::fmt::Write;
 use std::io::Write as IoWrite;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread;
 use std::sync::Arc;
 use std::time::Duration;
 use std::fmt::Display;
 use termcolor::{Color, ColorChoice, ColorSpec, WriteColor, Buffer, BufferWriter};
 
+mod cli;
+use cli::build_cli;
+
 const ADDRESS_LENGTH: usize = 40;
 const ADDRESS_BYTES: usize = ADDRESS_LENGTH / 2;
 const KECCAK_OUTPUT_BYTES: usize = 32;
 const ADDRESS_BYTE_INDEX: usize = KECCAK_OUTPUT_BYTES - ADDRESS_BYTES;
+/// How many keypairs a worker generates before flushing its local count into the shared
+/// iteration counter, so the hot loop doesn't synchronize on every single attempt.
+const ITERATION_BATCH_SIZE: u64 = 1024;
 
 lazy_static! {
     static ref ADDRESS_PATTERN: Regex = Regex::new(r"^[0-9a-f]{1,40}$").unwrap();
@@ -56,6 +65,34 @@ macro_rules! cprint {
 struct BruteforceResult {
     address: String,
     private_key: String,
+    score: Option<i64>,
+}
+
+/// An entry on the `--top` shortlist, ordered by score so it can live in a `BinaryHeap`.
+struct ScoredResult {
+    score: i64,
+    address: String,
+    private_key: String,
+}
+
+impl PartialEq for ScoredResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score
+    }
+}
+
+impl Eq for ScoredResult {}
+
+impl PartialOrd for ScoredResult {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredResult {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score.cmp(&other.score)
+    }
 }
 
 trait Pattern: Display + Send + Sync + Sized {
@@ -63,6 +100,17 @@ trait Pattern: Display + Send + Sync + Sized {
     fn parse<T: AsRef<str>>(string: T) -> Result<Self, String>;
     fn postprocess_vec(vec: &mut PatternVec<Self>);
     fn contains_vec(vec: &PatternVec<Self>, address: &String) -> bool;
+
+    /// Returns a measure of how well `string` matches this pattern, if the concept of a score
+    /// applies to this kind of pattern. Used to rank near-misses against each other.
+    fn score(&self, _string: &str) -> Option<i64> {
+        None
+    }
+
+    /// Returns the best score among the patterns in `vec`, or `None` if scoring doesn't apply.
+    fn score_vec(vec: &PatternVec<Self>, address: &String) -> Option<i64> {
+        vec.vec.iter().filter_map(|pattern| pattern.score(address)).max()
+    }
 }
 
 impl Pattern for Regex {
@@ -129,6 +177,120 @@ impl Pattern for String {
     }
 }
 
+/// The base score awarded for each pattern character that is successfully aligned.
+const FUZZY_BASE_MATCH_SCORE: i64 = 10;
+/// Extra score awarded when a match immediately follows the previous matched character,
+/// i.e. the two line up without any skipped address characters in between.
+const FUZZY_CONSECUTIVE_BONUS: i64 = 5;
+/// Extra score awarded when the very first pattern character matches address index 0.
+const FUZZY_START_BONUS: i64 = 3;
+/// Score subtracted per address character skipped between two consecutive pattern matches.
+const FUZZY_GAP_PENALTY: i64 = 1;
+
+/// Finds the best-scoring way to align `pattern` as an in-order subsequence of `address`,
+/// returning `None` when no such alignment exists.
+///
+/// `dp[i][j]` holds the best score for aligning the first `i` pattern characters so that the
+/// `i`-th one lands on address index `j`. Each entry is reached from some earlier `dp[i - 1][k]`
+/// with `k < j`, paying a gap penalty for the skipped address characters and collecting a bonus
+/// when the match directly follows the previous one.
+fn fuzzy_score(pattern: &str, address: &str) -> Option<i64> {
+    let pattern = pattern.as_bytes();
+    let address = address.as_bytes();
+    let pattern_length = pattern.len();
+    let address_length = address.len();
+
+    if pattern_length == 0 || pattern_length > address_length {
+        return None;
+    }
+
+    let mut dp = vec![vec![None; address_length]; pattern_length + 1];
+
+    for (j, &address_char) in address.iter().enumerate() {
+        if address_char == pattern[0] {
+            let mut score = FUZZY_BASE_MATCH_SCORE;
+
+            if j == 0 {
+                score += FUZZY_START_BONUS;
+            }
+
+            dp[1][j] = Some(score);
+        }
+    }
+
+    for i in 2..=pattern_length {
+        for j in (i - 1)..address_length {
+            if address[j] != pattern[i - 1] {
+                continue;
+            }
+
+            let best_previous = (i - 2..j)
+                .filter_map(|k| dp[i - 1][k].map(|previous_score| {
+                    let gap = (j - k - 1) as i64;
+                    let bonus = if gap == 0 { FUZZY_CONSECUTIVE_BONUS } else { 0 };
+
+                    previous_score - gap * FUZZY_GAP_PENALTY + bonus
+                }))
+                .max();
+
+            if let Some(best_previous) = best_previous {
+                dp[i][j] = Some(best_previous + FUZZY_BASE_MATCH_SCORE);
+            }
+        }
+    }
+
+    dp[pattern_length][(pattern_length - 1)..]
+        .iter()
+        .filter_map(|&score| score)
+        .max()
+}
+
+struct Fuzzy {
+    pattern: String,
+}
+
+impl Pattern for Fuzzy {
+    fn matches(&self, string: &str) -> bool {
+        self.score(string).is_some()
+    }
+
+    fn parse<T: AsRef<str>>(string: T) -> Result<Self, String> {
+        let pattern = string.as_ref().to_lowercase();
+
+        if !ADDRESS_PATTERN.is_match(&pattern) {
+            return Err("Pattern contains invalid characters".to_string());
+        }
+
+        Ok(Fuzzy { pattern })
+    }
+
+    fn postprocess_vec(_: &mut PatternVec<Self>) {
+        // Don't do anything
+    }
+
+    #[inline]
+    fn contains_vec(vec: &PatternVec<Self>, address: &String) -> bool {
+        // Linear search
+        for pattern in &vec.vec {
+            if pattern.matches(address) {
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    fn score(&self, string: &str) -> Option<i64> {
+        fuzzy_score(&self.pattern, string)
+    }
+}
+
+impl Display for Fuzzy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
 struct PatternVec<P: Pattern> {
     vec: Vec<P>,
 }
@@ -193,6 +355,10 @@ impl<P: Pattern> PatternVec<P> {
     fn contains(&self, address: &String) -> bool {
         <P as Pattern>::contains_vec(self, address)
     }
+
+    fn score(&self, address: &String) -> Option<i64> {
+        <P as Pattern>::score_vec(self, address)
+    }
 }
 
 fn parse_color_choice(string: &str) -> Result<ColorChoice, ()> {
@@ -215,52 +381,21 @@ fn to_hex_string(slice: &[u8], expected_string_size: usize) -> String {
     result
 }
 
+/// Generates a random keypair and returns the hex-encoded Ethereum address it derives to,
+/// along with the hex-encoded private key that produced it.
+fn generate_address(alg: &Secp256k1, rng: &mut OsRng) -> (String, String) {
+    let (private_key, public_key) = alg.generate_keypair(rng)
+        .expect("Could not generate a random keypair. Please file a GitHub issue.");
+    let public_key_array = &public_key.serialize_vec(alg, false)[1..];
+    let keccak = tiny_keccak::keccak256(public_key_array);
+    let address = to_hex_string(&keccak[ADDRESS_BYTE_INDEX..], 40);  // get rid of the constant 0x04 byte
+    let private_key = to_hex_string(&private_key[..], 64);
+
+    (address, private_key)
+}
+
 fn main() {
-    let matches = app_from_crate!()
-        .arg(Arg::with_name("regexp")
-             .long("regexp")
-             .short("e")
-             .help("Use regex pattern matching")
-             .long_help("By default, an address is accepted when the beginning matches one of the
-strings provided as the patterns. This flag changes the functionality from
-plain string matching to regex pattern matching."))
-        .arg(Arg::with_name("quiet")
-             .long("quiet")
-             .short("q")
-             .help("Output only the results")
-             .long_help("Output only the resulting address and private key separated by a space."))
-        .arg(Arg::with_name("color")
-             .long("color")
-             .short("c")
-             .help("Changes the color formatting strategy")
-             .long_help("Changes the color formatting strategy in the following way:
-    always      -- Try very hard to emit colors. This includes
-                   emitting ANSI colors on Windows if the console
-                   API is unavailable.
-    always_ansi -- like always, except it never tries to use
-                   anything other than emitting ANSI color codes.
-    auto        -- Try to use colors, but don't force the issue.
-                   If the console isn't available on Windows, or
-                   if TERM=dumb, for example, then don't use colors.
-    never       -- Never emit colors.\n")
-             .takes_value(true)
-             .possible_values(&["always", "always_ansi", "auto", "never"])
-             .default_value("auto"))
-        .arg(Arg::with_name("stream")
-             .long("stream")
-             .short("s")
-             .help("Keep outputting results")
-             .long_help("Instead of outputting a single result, keep outputting until terminated."))
-        .arg(Arg::with_name("PATTERN")
-             .help("The pattern to match the address against")
-             .long_help("The pattern to match the address against.
-If no patterns are provided, they are read from the stdin (standard input),
-where each pattern is on a separate line.
-Addresses are outputted if the beginning matches one of these patterns.
-If the `--regexp` flag is used, the addresses are matched against these
-patterns as regex patterns, which replaces the basic string comparison.")
-             .multiple(true))
-        .get_matches();
+    let matches = build_cli().get_matches();
 
     let quiet = matches.is_present("quiet");
     let color_choice = parse_color_choice(matches.value_of("color").unwrap()).unwrap();
@@ -268,6 +403,8 @@ patterns as regex patterns, which replaces the basic string comparison.")
 
     if matches.is_present("regexp") {
         main_pattern_type_selected::<Regex>(matches, quiet, buffer_writer);
+    } else if matches.is_present("fuzzy") {
+        main_pattern_type_selected::<Fuzzy>(matches, quiet, buffer_writer);
     } else {
         main_pattern_type_selected::<String>(matches, quiet, buffer_writer);
     }
@@ -325,12 +462,19 @@ fn main_pattern_type_selected<P: Pattern + 'static>(matches: ArgMatches, quiet:
         buffer_writer.lock().unwrap().print(&stdout).expect("Could not write to stdout.");
     }
 
+    if let Some(top_n) = matches.value_of("top") {
+        let top_n: usize = top_n.parse().expect("Validated by clap above.");
+        run_top_n_mode(patterns, quiet, buffer_writer, top_n);
+        return;
+    }
+
     let thread_count = num_cpus::get();
 
     loop {
         let mut threads = Vec::with_capacity(thread_count);
         let result: Arc<Mutex<Option<BruteforceResult>>> = Arc::new(Mutex::new(None));
-        let iterations_this_second: Arc<Mutex<u32>> = Arc::new(Mutex::new(0));
+        let found = Arc::new(AtomicBool::new(false));
+        let iterations_this_second = Arc::new(AtomicU64::new(0));
         let alg = Arc::new(Secp256k1::new());
         let working_threads = Arc::new(Mutex::new(thread_count));
 
@@ -338,39 +482,45 @@ fn main_pattern_type_selected<P: Pattern + 'static>(matches: ArgMatches, quiet:
             let working_threads = working_threads.clone();
             let patterns = patterns.clone();
             let result = result.clone();
+            let found = found.clone();
             let alg = alg.clone();
             let iterations_this_second = iterations_this_second.clone();
 
             threads.push(thread::spawn(move || {
+                let mut rng = OsRng::new()
+                    .expect("Could not create a secure random number generator. Please file a GitHub issue.");
+                let mut iterations_this_batch: u64 = 0;
+
                 'dance:
                 loop {
-                    {
-                        let result_guard = result.lock().unwrap();
-
-                        if let Some(_) = *result_guard {
-                            break 'dance;
-                        }
+                    if found.load(Ordering::Relaxed) {
+                        break 'dance;
                     }
 
-                    let mut rng = OsRng::new()
-                        .expect("Could not create a secure random number generator. Please file a GitHub issue.");
-                    let (private_key, public_key) = alg.generate_keypair(&mut rng)
-                        .expect("Could not generate a random keypair. Please file a GitHub issue.");
-                    let public_key_array = &public_key.serialize_vec(&alg, false)[1..];
-                    let keccak = tiny_keccak::keccak256(public_key_array);
-                    let address = to_hex_string(&keccak[ADDRESS_BYTE_INDEX..], 40);  // get rid of the constant 0x04 byte
+                    let (address, private_key) = generate_address(&alg, &mut rng);
 
                     if patterns.contains(&address) {
-                        *result.lock().unwrap() = Some(BruteforceResult {
-                            address,
-                            private_key: to_hex_string(&private_key[..], 64),
-                        });
+                        if !found.swap(true, Ordering::Relaxed) {
+                            let score = patterns.score(&address);
+                            *result.lock().unwrap() = Some(BruteforceResult {
+                                address,
+                                private_key,
+                                score,
+                            });
+                        }
+
                         break 'dance;
                     }
 
-                    *iterations_this_second.lock().unwrap() += 1;
+                    iterations_this_batch += 1;
+
+                    if iterations_this_batch >= ITERATION_BATCH_SIZE {
+                        iterations_this_second.fetch_add(iterations_this_batch, Ordering::Relaxed);
+                        iterations_this_batch = 0;
+                    }
                 }
 
+                iterations_this_second.fetch_add(iterations_this_batch, Ordering::Relaxed);
                 *working_threads.lock().unwrap() -= 1;
             }));
         }
@@ -382,30 +532,25 @@ fn main_pattern_type_selected<P: Pattern + 'static>(matches: ArgMatches, quiet:
         {
             let buffer_writer = buffer_writer.clone();
             let sync_buffer = sync_buffer.clone();
-            let result = result.clone();
+            let found = found.clone();
+            let iterations_this_second = iterations_this_second.clone();
 
             thread::spawn(move || 'dance: loop {
                               thread::sleep(Duration::from_secs(1));
 
-                              {
-                                  let result_guard = result.lock().unwrap();
-
-                                  if let Some(_) = *result_guard {
-                                      break 'dance;
-                                  }
+                              if found.load(Ordering::Relaxed) {
+                                  break 'dance;
                               }
 
+                              let iterations_per_second = iterations_this_second.swap(0, Ordering::Relaxed);
                               let mut buffer = buffer_writer.lock().unwrap().buffer();
-                              let mut iterations_per_second =
-                                  iterations_this_second.lock().unwrap();
                               cprint!(quiet,
                                       buffer,
                                       Color::Cyan,
                                       "{}",
-                                      *iterations_per_second);
+                                      iterations_per_second);
                               cprintln!(quiet, buffer, Color::White, " addresses / second");
                               *sync_buffer.lock().unwrap() = Some(buffer);
-                              *iterations_per_second = 0;
                           });
         }
 
@@ -449,6 +594,12 @@ fn main_pattern_type_selected<P: Pattern + 'static>(matches: ArgMatches, quiet:
                       Color::Red,
                       "{}",
                       result.private_key);
+
+            if let Some(score) = result.score {
+                cprint!(quiet, stdout, Color::White, "Fuzzy match score: ");
+                cprintln!(quiet, stdout, Color::Cyan, "{}", score);
+            }
+
             cprintln!(quiet,
                       stdout,
                       Color::White,
@@ -465,7 +616,10 @@ fn main_pattern_type_selected<P: Pattern + 'static>(matches: ArgMatches, quiet:
         }
 
         if quiet {
-            println!("0x{} {}", result.address, result.private_key);
+            match result.score {
+                Some(score) => println!("0x{} {} {}", result.address, result.private_key, score),
+                None => println!("0x{} {}", result.address, result.private_key),
+            }
         }
 
         if !matches.is_present("stream") {
@@ -473,3 +627,82 @@ fn main_pattern_type_selected<P: Pattern + 'static>(matches: ArgMatches, quiet:
         }
     }
 }
+
+/// Runs `--stream --top N` mode: every worker keeps generating addresses forever, and whenever
+/// one scores better than the current worst entry on the shared shortlist, it takes that entry's
+/// place and the improvement is printed immediately. Never returns; the user terminates it.
+fn run_top_n_mode<P: Pattern + 'static>(patterns: Arc<PatternVec<P>>,
+                                         quiet: bool,
+                                         buffer_writer: Arc<Mutex<BufferWriter>>,
+                                         top_n: usize) {
+    {
+        let mut stdout = buffer_writer.lock().unwrap().buffer();
+        cprint!(quiet, stdout, Color::White, "Collecting the top ");
+        cprint!(quiet, stdout, Color::Cyan, "{}", top_n);
+        cprintln!(quiet, stdout, Color::White, " best-scoring addresses. Press Ctrl+C to stop.");
+        buffer_writer.lock().unwrap().print(&stdout).expect("Could not write to stdout.");
+    }
+
+    let thread_count = num_cpus::get();
+    let alg = Arc::new(Secp256k1::new());
+    let heap: Arc<Mutex<BinaryHeap<Reverse<ScoredResult>>>> =
+        Arc::new(Mutex::new(BinaryHeap::with_capacity(top_n)));
+    let mut threads = Vec::with_capacity(thread_count);
+
+    for _ in 0..thread_count {
+        let patterns = patterns.clone();
+        let alg = alg.clone();
+        let heap = heap.clone();
+        let buffer_writer = buffer_writer.clone();
+
+        threads.push(thread::spawn(move || {
+            let mut rng = OsRng::new()
+                .expect("Could not create a secure random number generator. Please file a GitHub issue.");
+
+            loop {
+                let (address, private_key) = generate_address(&alg, &mut rng);
+
+                let score = match patterns.score(&address) {
+                    Some(score) => score,
+                    None => continue,
+                };
+
+                let mut heap_guard = heap.lock().unwrap();
+                let improves = heap_guard.len() < top_n ||
+                    heap_guard.peek().map_or(true, |Reverse(worst)| score > worst.score);
+
+                if !improves {
+                    continue;
+                }
+
+                if heap_guard.len() >= top_n {
+                    heap_guard.pop();
+                }
+
+                heap_guard.push(Reverse(ScoredResult {
+                    score,
+                    address: address.clone(),
+                    private_key: private_key.clone(),
+                }));
+                drop(heap_guard);
+
+                let mut stdout = buffer_writer.lock().unwrap().buffer();
+                cprint!(quiet, stdout, Color::White, "New top score ");
+                cprint!(quiet, stdout, Color::Cyan, "{}", score);
+                cprint!(quiet, stdout, Color::White, ": ");
+                cprint!(quiet, stdout, Color::Yellow, "0x{}", address);
+                cprint!(quiet, stdout, Color::White, " ");
+                cprintln!(quiet, stdout, Color::Red, "{}", private_key);
+                buffer_writer.lock().unwrap().print(&stdout).expect("Could not write to stdout.");
+
+                if quiet {
+                    println!("0x{} {} {}", address, private_key, score);
+                }
+            }
+        }));
+    }
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}